@@ -0,0 +1,282 @@
+use aes_gcm::aead::Aead;
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use anyhow::{anyhow, Result};
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::Password;
+use keyring::Entry;
+use octocrab::auth::OAuth;
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use tracing::debug;
+
+const KEYRING_SERVICE: &str = "fj";
+const KEYRING_USER: &str = "github";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AuthConfig {
+    pub access_token: String,
+    pub token_type: String,
+    pub scope: Vec<String>,
+    // Not part of the GitHub OAuth flow; populated by `fj login --gitlab
+    // <host>`/`--gitea <host>`, which just asks for a personal access
+    // token and stores it here instead of running the device flow.
+    // Keyed by host, e.g. `{"gitlab.com": "...", "gitea.corp": "..."}`,
+    // so a token issued for one instance never leaks to another (the
+    // same scoping `host` gives the GitHub path below).
+    #[serde(default)]
+    pub forge_tokens: HashMap<String, String>,
+    // GitHub Enterprise host this token is valid for, e.g. `ghe.corp`.
+    // `None` means plain github.com.
+    #[serde(default)]
+    pub host: Option<String>,
+}
+
+impl From<OAuth> for AuthConfig {
+    fn from(oauth: OAuth) -> Self {
+        Self {
+            access_token: oauth.access_token.expose_secret().to_string(),
+            token_type: oauth.token_type,
+            scope: oauth.scope,
+            forge_tokens: HashMap::new(),
+            host: None,
+        }
+    }
+}
+
+impl AuthConfig {
+    /// Persists this config to the OS keyring. Falls back to an
+    /// encrypted file (passphrase-derived key) on machines without a
+    /// keyring backend, e.g. a headless server.
+    pub fn store(&self) -> Result<()> {
+        let payload = toml::to_string(self)?;
+
+        match Entry::new(KEYRING_SERVICE, KEYRING_USER) {
+            Ok(entry) => match entry.set_password(&payload) {
+                Ok(()) => Ok(()),
+                Err(err) => {
+                    debug!("keyring rejected the token ({err}), falling back to an encrypted file");
+                    EncryptedFile::new()?.store(&payload)
+                }
+            },
+            Err(err) => {
+                debug!("no keyring backend available ({err}), falling back to an encrypted file");
+                EncryptedFile::new()?.store(&payload)
+            }
+        }
+    }
+
+    /// Loads the config, preferring the keyring, then the encrypted
+    /// file, then the legacy plaintext `github.toml`. A config found in
+    /// the legacy file is migrated into secure storage and the
+    /// plaintext copy is removed.
+    pub fn load() -> Result<Self> {
+        if let Ok(entry) = Entry::new(KEYRING_SERVICE, KEYRING_USER) {
+            if let Ok(payload) = entry.get_password() {
+                return Ok(toml::from_str(&payload)?);
+            }
+        }
+
+        if let Ok(payload) = EncryptedFile::new()?.load() {
+            return Ok(toml::from_str(&payload)?);
+        }
+
+        let config = Self::load_legacy_plaintext()?;
+        if let Err(err) = config.store() {
+            debug!("failed to migrate plaintext auth config into secure storage: {err}");
+        } else if let Err(err) = Self::remove_legacy_plaintext() {
+            debug!("failed to remove migrated plaintext auth config: {err}");
+        }
+
+        Ok(config)
+    }
+
+    /// Removes the stored config from every backend it might be in.
+    pub fn clear() -> Result<()> {
+        if let Ok(entry) = Entry::new(KEYRING_SERVICE, KEYRING_USER) {
+            let _ = entry.delete_credential();
+        }
+        EncryptedFile::new()?.remove()?;
+        let _ = Self::remove_legacy_plaintext();
+
+        Ok(())
+    }
+
+    fn load_legacy_plaintext() -> Result<Self> {
+        let base_dirs = xdg::BaseDirectories::with_prefix("fj")?;
+        let config_file_path = base_dirs.get_config_file("github.toml");
+        let config_file = fs::read_to_string(config_file_path)?;
+        Ok(toml::from_str(&config_file)?)
+    }
+
+    fn remove_legacy_plaintext() -> Result<()> {
+        let base_dirs = xdg::BaseDirectories::with_prefix("fj")?;
+        let config_file_path = base_dirs.place_config_file("github.toml")?;
+        if config_file_path.exists() {
+            fs::remove_file(config_file_path)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Portable fallback for machines without a keyring (Secret Service,
+// Keychain, Credential Manager): an AES-256-GCM encrypted file with a
+// key derived from a passphrase via scrypt. Layout on disk is
+// `salt || nonce || ciphertext`.
+struct EncryptedFile {
+    path: PathBuf,
+}
+
+impl EncryptedFile {
+    fn new() -> Result<Self> {
+        let base_dirs = xdg::BaseDirectories::with_prefix("fj")?;
+        Ok(Self {
+            path: base_dirs.place_config_file("github.enc")?,
+        })
+    }
+
+    fn passphrase(&self) -> Result<String> {
+        if let Ok(passphrase) = std::env::var("FJ_AUTH_PASSPHRASE") {
+            return Ok(passphrase);
+        }
+
+        // Same rationale as `require_interactive_stderr` in main.rs: a
+        // prompt is meaningless without a real terminal, and a script
+        // relying on this fallback should fail fast instead of hanging
+        // forever waiting for input that will never come.
+        if !std::io::stderr().is_terminal() {
+            return Err(anyhow!(
+                "no keyring backend available and FJ_AUTH_PASSPHRASE isn't set; \
+                 set it to unlock the encrypted auth file non-interactively"
+            ));
+        }
+
+        Ok(Password::with_theme(&ColorfulTheme::default())
+            .with_prompt("Passphrase to protect the stored GitHub token")
+            .interact()?)
+    }
+
+    fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32]> {
+        let mut key = [0u8; 32];
+        scrypt::scrypt(
+            passphrase.as_bytes(),
+            salt,
+            &scrypt::Params::recommended(),
+            &mut key,
+        )
+        .map_err(|err| anyhow!("failed to derive encryption key: {err}"))?;
+
+        Ok(key)
+    }
+
+    fn store(&self, payload: &str) -> Result<()> {
+        let passphrase = self.passphrase()?;
+
+        let mut salt = [0u8; SALT_LEN];
+        getrandom::getrandom(&mut salt)?;
+        let key = Self::derive_key(&passphrase, &salt)?;
+
+        let mut nonce_bytes = [0u8; NONCE_LEN];
+        getrandom::getrandom(&mut nonce_bytes)?;
+
+        let cipher = Aes256Gcm::new_from_slice(&key).map_err(|err| anyhow!("{err}"))?;
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce_bytes), payload.as_bytes())
+            .map_err(|err| anyhow!("failed to encrypt auth config: {err}"))?;
+
+        let mut out = Vec::with_capacity(salt.len() + nonce_bytes.len() + ciphertext.len());
+        out.extend_from_slice(&salt);
+        out.extend_from_slice(&nonce_bytes);
+        out.extend_from_slice(&ciphertext);
+
+        fs::write(&self.path, out)?;
+        Ok(())
+    }
+
+    fn load(&self) -> Result<String> {
+        let data = fs::read(&self.path)?;
+        if data.len() < SALT_LEN + NONCE_LEN {
+            return Err(anyhow!("encrypted auth file is corrupt"));
+        }
+
+        let (salt, rest) = data.split_at(SALT_LEN);
+        let (nonce_bytes, ciphertext) = rest.split_at(NONCE_LEN);
+
+        let passphrase = self.passphrase()?;
+        let key = Self::derive_key(&passphrase, salt)?;
+        let cipher = Aes256Gcm::new_from_slice(&key).map_err(|err| anyhow!("{err}"))?;
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce_bytes), ciphertext)
+            .map_err(|_| anyhow!("failed to decrypt auth config; wrong passphrase?"))?;
+
+        Ok(String::from_utf8(plaintext)?)
+    }
+
+    fn remove(&self) -> Result<()> {
+        if self.path.exists() {
+            fs::remove_file(&self.path)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypt_decrypt_roundtrips_with_the_same_passphrase() {
+        let salt = [7u8; SALT_LEN];
+        let nonce_bytes = [9u8; NONCE_LEN];
+        let key = EncryptedFile::derive_key("correct horse battery staple", &salt).unwrap();
+        let cipher = Aes256Gcm::new_from_slice(&key).unwrap();
+        let nonce = Nonce::from_slice(&nonce_bytes);
+
+        let ciphertext = cipher.encrypt(nonce, b"super secret payload".as_slice()).unwrap();
+        let plaintext = cipher.decrypt(nonce, ciphertext.as_slice()).unwrap();
+
+        assert_eq!(plaintext, b"super secret payload");
+    }
+
+    #[test]
+    fn wrong_passphrase_fails_to_decrypt() {
+        let salt = [7u8; SALT_LEN];
+        let nonce_bytes = [9u8; NONCE_LEN];
+        let right_key = EncryptedFile::derive_key("correct horse battery staple", &salt).unwrap();
+        let wrong_key = EncryptedFile::derive_key("incorrect horse", &salt).unwrap();
+
+        let cipher = Aes256Gcm::new_from_slice(&right_key).unwrap();
+        let nonce = Nonce::from_slice(&nonce_bytes);
+        let ciphertext = cipher.encrypt(nonce, b"super secret payload".as_slice()).unwrap();
+
+        let wrong_cipher = Aes256Gcm::new_from_slice(&wrong_key).unwrap();
+        assert!(wrong_cipher.decrypt(nonce, ciphertext.as_slice()).is_err());
+    }
+
+    #[test]
+    fn store_then_load_roundtrips_through_a_real_file() {
+        std::env::set_var("FJ_AUTH_PASSPHRASE", "correct horse battery staple");
+
+        let file = EncryptedFile {
+            path: std::env::temp_dir().join(format!(
+                "fj-auth-test-{}-{}.enc",
+                std::process::id(),
+                line!()
+            )),
+        };
+
+        file.store("super secret payload").unwrap();
+        let loaded = file.load().unwrap();
+        file.remove().unwrap();
+        std::env::remove_var("FJ_AUTH_PASSPHRASE");
+
+        assert_eq!(loaded, "super secret payload");
+    }
+}
@@ -0,0 +1,147 @@
+use anyhow::{anyhow, Result};
+use git_url_parse::GitUrl;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use which::which;
+
+/// An owner/repo pair plus the host it was parsed from, e.g.
+/// `github.com`, `gitlab.com`, or a self-hosted forge. `select_provider`
+/// uses the host to pick which forge's API to talk to.
+#[derive(Debug)]
+pub struct Repository {
+    pub host: String,
+    pub owner: String,
+    pub repo: String,
+}
+
+pub struct Git<'a> {
+    bin: PathBuf,
+    cwd: Option<&'a Path>,
+}
+
+impl<'a> Git<'a> {
+    pub fn new(cwd: Option<&'a Path>) -> Result<Git<'a>> {
+        let bin = which("git")?;
+        Ok(Self { bin, cwd })
+    }
+
+    fn cmd(&self) -> Command {
+        let mut cmd = Command::new(&self.bin);
+        if let Some(cwd) = &self.cwd {
+            cmd.current_dir(cwd);
+        }
+        cmd
+    }
+
+    pub async fn get_branches(&self) -> Result<Vec<String>> {
+        let output = self
+            .cmd()
+            .arg("branch")
+            .arg("--format='%(refname:short)'")
+            .output()
+            .await?;
+        let str = String::from_utf8(output.stdout)?;
+
+        Ok(str
+            .lines()
+            .map(|l| {
+                let l = l.trim();
+                let l = l.strip_prefix('\'').unwrap_or(l);
+                let l = l.strip_suffix('\'').unwrap_or(l);
+
+                l.to_string()
+            })
+            .collect())
+    }
+
+    // Gets the ref as a full commit SHA. Forges are inconsistent about
+    // matching on an abbreviated SHA in their CI-status APIs (GitLab's
+    // pipeline `sha` filter in particular only matches the full 40-char
+    // SHA it stored), so every provider gets the unabbreviated form.
+    pub async fn get_ref_as_commit(&self, git_ref: &str) -> Result<String> {
+        let output = self.cmd().arg("rev-parse").arg(git_ref).output().await?;
+
+        let git_ref = String::from_utf8(output.stdout)?;
+        Ok(git_ref.trim().to_string())
+    }
+
+    // Gets the name version of ref, i.e. `main`
+    pub async fn get_ref_as_name(&self, git_ref: &str) -> Result<String> {
+        let output = self
+            .cmd()
+            .arg("rev-parse")
+            .arg("--abbrev-ref")
+            .arg(git_ref)
+            .output()
+            .await?;
+
+        let git_ref = String::from_utf8(output.stdout)?;
+        Ok(git_ref.trim().to_string())
+    }
+
+    // Picks which remote to read: an explicitly requested one, else
+    // `origin`, else whatever remote the current branch tracks. This
+    // lets `fj` work in clones where `origin` isn't the pushable remote.
+    async fn resolve_remote(&self, requested: Option<&str>) -> Result<String> {
+        if let Some(remote) = requested {
+            return Ok(remote.to_string());
+        }
+
+        let origin = self
+            .cmd()
+            .arg("config")
+            .arg("--get")
+            .arg("remote.origin.url")
+            .output()
+            .await?;
+        if !String::from_utf8(origin.stdout)?.trim().is_empty() {
+            return Ok("origin".to_string());
+        }
+
+        let output = self
+            .cmd()
+            .arg("rev-parse")
+            .arg("--abbrev-ref")
+            .arg("--symbolic-full-name")
+            .arg("@{u}")
+            .output()
+            .await?;
+        let upstream = String::from_utf8(output.stdout)?;
+        let upstream = upstream.trim();
+        let remote = upstream.split('/').next().filter(|r| !r.is_empty()).ok_or_else(|| {
+            anyhow!("unable to determine which remote to use; pass `--remote <name>` explicitly")
+        })?;
+
+        Ok(remote.to_string())
+    }
+
+    // Reads `remote.<name>.url` (defaulting and falling back per
+    // `resolve_remote`) and parses it to get host/owner/repo. Handles
+    // both HTTPS and `git@host:owner/repo.git`-style SSH remotes.
+    pub async fn get_repository(&self, remote: Option<&str>) -> Result<Repository> {
+        let remote = self.resolve_remote(remote).await?;
+
+        let output = self
+            .cmd()
+            .arg("config")
+            .arg("--get")
+            .arg(format!("remote.{remote}.url"))
+            .output()
+            .await?;
+
+        let url = String::from_utf8(output.stdout)?;
+        let url = url.trim();
+        let git_url = GitUrl::parse(url).map_err(|_| {
+            anyhow!("unable to parse git remote `{remote}`. Try pointing `fj` at a different remote with `--remote`")
+        })?;
+        Ok(Repository {
+            host: git_url
+                .host
+                .ok_or(anyhow!("unable to determine the host of the git remote"))?,
+            owner: git_url.owner.ok_or_else(|| {
+                anyhow!("unable to determine the owner from git remote `{remote}`. Try pointing `fj` at a different remote with `--remote`")
+            })?,
+            repo: git_url.name,
+        })
+    }
+}
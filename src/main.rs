@@ -1,34 +1,48 @@
+mod auth;
+mod git;
+mod provider;
+
 use anyhow::{anyhow, Result};
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use colored::*;
 use dialoguer::console::Term;
-use dialoguer::{theme::ColorfulTheme, FuzzySelect};
+use dialoguer::{theme::ColorfulTheme, FuzzySelect, Password};
 use either::Either;
-use git_url_parse::GitUrl;
 use http::header::ACCEPT;
-use octocrab::auth::{Continue, OAuth};
-use octocrab::models::checks::ListCheckRuns;
-use octocrab::params::repos::Commitish;
-use octocrab::OctocrabBuilder;
-use secrecy::{ExposeSecret, SecretString};
-use serde::{Deserialize, Serialize};
-use std::fs;
-use std::path::{Path, PathBuf};
+use octocrab::auth::Continue;
+use secrecy::SecretString;
+use std::io::IsTerminal;
+use std::path::PathBuf;
 use std::str::FromStr;
-use std::sync::Arc;
-use tokio::process::Command;
+use std::time::{Duration, Instant};
 use tracing::debug;
-use which::which;
+
+use auth::AuthConfig;
+use git::Git;
+use provider::CheckRun;
 
 #[derive(Debug, Parser)]
 #[command(author, version, about)]
 struct CLIArgs {
     #[arg(long, short)]
     cwd: Option<PathBuf>,
+    /// Remote to read the repository from (defaults to `origin`, falling
+    /// back to the current branch's upstream remote)
+    #[arg(long)]
+    remote: Option<String>,
+    /// Output format for `status`/`branch`
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text, global = true)]
+    output: OutputFormat,
     #[command(subcommand)]
     command: CLICommand,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
 #[derive(Debug, Clone, Subcommand)]
 enum CLICommand {
     #[command(alias = "s")]
@@ -37,126 +51,96 @@ enum CLICommand {
     Open,
     #[command(alias = "b")]
     Branch,
-    Login,
+    /// Download and print a check run's log output
+    #[command(alias = "l")]
+    Logs {
+        /// Only print the last N lines
+        #[arg(long)]
+        tail: Option<usize>,
+    },
+    /// Poll check runs until they all finish, exiting non-zero if any failed
+    #[command(alias = "w")]
+    Watch {
+        /// Give up after this many seconds if checks are still pending
+        #[arg(long)]
+        timeout: Option<u64>,
+    },
+    /// Re-dispatch failed or cancelled check runs
+    #[command(alias = "rr")]
+    Rerun {
+        /// Rerun every failed/cancelled check run without prompting
+        #[arg(long)]
+        failed: bool,
+    },
+    Login {
+        /// GitHub host to authenticate against, for GitHub Enterprise
+        #[arg(long, default_value = "github.com")]
+        host: String,
+        /// Store a personal access token for a GitLab host instead of
+        /// running the GitHub device flow
+        #[arg(long, value_name = "HOST", conflicts_with = "gitea")]
+        gitlab: Option<String>,
+        /// Store a personal access token for a Gitea host instead of
+        /// running the GitHub device flow
+        #[arg(long, value_name = "HOST", conflicts_with = "gitlab")]
+        gitea: Option<String>,
+    },
     Logout,
 }
 
-#[derive(Debug)]
-struct GitHubRepository {
-    owner: String,
-    repo: String,
-}
-
-struct Git<'a> {
-    bin: PathBuf,
-    cwd: Option<&'a Path>,
-}
-
 const GITHUB_CLIENT_ID: &str = "Iv1.6759afe4a207433f";
 
-impl<'a> Git<'a> {
-    fn new(cwd: Option<&'a Path>) -> Result<Git<'a>> {
-        let bin = which("git")?;
-        Ok(Self { bin, cwd })
-    }
-
-    fn cmd(&self) -> Command {
-        let mut cmd = Command::new(&self.bin);
-        if let Some(cwd) = &self.cwd {
-            cmd.current_dir(cwd);
-        }
-        cmd
-    }
-
-    async fn get_branches(&self) -> Result<Vec<String>> {
-        let output = self
-            .cmd()
-            .arg("branch")
-            .arg("--format='%(refname:short)'")
-            .output()
-            .await?;
-        let str = String::from_utf8(output.stdout)?;
-
-        Ok(str
-            .lines()
-            .map(|l| {
-                let l = l.trim();
-                let l = l.strip_prefix('\'').unwrap_or(l);
-                let l = l.strip_suffix('\'').unwrap_or(l);
-
-                l.to_string()
-            })
-            .collect())
-    }
-
-    // Gets the ref as a short commit
-    async fn get_ref_as_commit(&self, git_ref: &str) -> Result<String> {
-        let output = self
-            .cmd()
-            .arg("rev-parse")
-            .arg("--short")
-            .arg(git_ref)
-            .output()
-            .await?;
-
-        let git_ref = String::from_utf8(output.stdout)?;
-        Ok(git_ref.trim().to_string())
-    }
+// Machine-readable counterpart to `print_check_runs`, matching the
+// stable `{name, status, conclusion, url}` schema scripts can depend on.
+fn print_check_runs_json(runs: &[CheckRun]) -> Result<()> {
+    println!("{}", serde_json::to_string(runs)?);
+    Ok(())
+}
 
-    // Gets the name version of ref, i.e. `main`
-    async fn get_ref_as_name(&self, git_ref: &str) -> Result<String> {
-        let output = self
-            .cmd()
-            .arg("rev-parse")
-            .arg("--abbrev-ref")
-            .arg(git_ref)
-            .output()
-            .await?;
-
-        let git_ref = String::from_utf8(output.stdout)?;
-        Ok(git_ref.trim().to_string())
+// 0 = every run finished and none failed, 1 = at least one failed/was
+// cancelled/timed out, 2 = at least one run is still pending (or no runs
+// were found). Lets `fj status` (or `branch`)
+// gate a shell script or pre-push hook the same way a CI job would.
+fn exit_code_for_runs(runs: &[CheckRun]) -> i32 {
+    if runs
+        .iter()
+        .any(|run| matches!(run.conclusion.as_deref(), Some("failure" | "timed_out" | "cancelled")))
+    {
+        1
+    } else if !runs.is_empty() && runs.iter().all(|run| run.conclusion.is_some()) {
+        0
+    } else {
+        2
     }
+}
 
-    // Uses `git config --get remote.origin.url` to get url and parses
-    // it to get owner/repo
-    async fn get_github_repo(&self) -> Result<GitHubRepository> {
-        let output = self
-            .cmd()
-            .arg("config")
-            .arg("--get")
-            .arg("remote.origin.url")
-            .output()
-            .await?;
-
-        let url = String::from_utf8(output.stdout)?;
-        let url = url.trim();
-        let git_url = GitUrl::parse(url).map_err(|_| anyhow!("unable to parse git remote. Please supply the owner and repository name manually with `--owner` and `--repo`"))?;
-        Ok(GitHubRepository {
-            owner: git_url.owner.ok_or(anyhow!("unable to parse git remote. Please supply the owner and repository name manually with `--owner` and `--repo`"))?,
-            repo: git_url.name,
-        })
+// Interactive commands (`open`, `logs`, `branch`) prompt with a
+// `FuzzySelect` on stderr; that's meaningless without a real terminal,
+// so fail fast instead of hanging a script waiting for input.
+fn require_interactive_stderr() -> Result<()> {
+    if std::io::stderr().is_terminal() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "this command needs an interactive terminal to make a selection; try `fj status --output json` in scripts"
+        ))
     }
 }
 
-fn print_check_runs(git_ref: &str, runs: ListCheckRuns) {
-    println!("Found {} runs for {}\n", runs.total_count, git_ref);
-    let max_len = runs
-        .check_runs
-        .iter()
-        .map(|run| run.name.len())
-        .max()
-        .unwrap_or_default();
+fn print_check_runs(git_ref: &str, runs: &[CheckRun]) {
+    println!("Found {} runs for {}\n", runs.len(), git_ref);
+    let max_len = runs.iter().map(|run| run.name.len()).max().unwrap_or_default();
 
-    for run in runs.check_runs {
+    for run in runs {
         let conclusion = match run.conclusion.as_deref() {
-            Some("success") => "ðŸŸ¢",
-            Some("failure") => "ðŸ”´",
-            Some("neutral") => "âšª",
-            Some("cancelled") => "âŒ",
-            Some("timed_out") => "âŒ›",
-            Some("action_required") => "ðŸ”§",
+            Some("success") => "🟢",
+            Some("failure") => "🔴",
+            Some("neutral") => "⚪",
+            Some("cancelled") => "❌",
+            Some("timed_out") => "⌛",
+            Some("action_required") => "🔧",
             Some(conclusion) => conclusion,
-            None => "ðŸŸ¡",
+            None => "🟡",
         };
 
         println!(
@@ -168,109 +152,278 @@ fn print_check_runs(git_ref: &str, runs: ListCheckRuns) {
     }
 }
 
-async fn get_runs_for_ref(cwd: Option<&Path>, git_ref: &str) -> Result<(ListCheckRuns, String)> {
+// Builds the right `CiProvider` for the current repo's remote, along
+// with the parsed owner/repo/host it resolved. Shared by every command
+// that needs to talk to a forge.
+async fn build_provider(
+    git: &Git<'_>,
+    remote: Option<&str>,
+) -> Result<(Box<dyn provider::CiProvider>, git::Repository)> {
+    let repo = git.get_repository(remote).await?;
+    let auth = AuthConfig::load().ok();
+    if auth.is_none() {
+        debug!("no authentication config found, falling back to an unauthenticated client");
+    }
+
+    let token = if repo.host.contains("gitlab") || repo.host.contains("gitea") {
+        auth.and_then(|auth| auth.forge_tokens.get(&repo.host).cloned())
+    } else {
+        // Only hand over the token if it was issued for this host: a
+        // github.com token should never be sent to a GitHub Enterprise
+        // instance (or vice versa).
+        auth.filter(|auth| auth.host.as_deref().unwrap_or("github.com") == repo.host)
+            .map(|auth| auth.access_token)
+    };
+
+    let provider = provider::select_provider(&repo.host, token)?;
+    Ok((provider, repo))
+}
+
+async fn get_runs_for_ref(
+    cwd: Option<&std::path::Path>,
+    remote: Option<&str>,
+    git_ref: &str,
+) -> Result<(Vec<CheckRun>, String)> {
     let git = Git::new(cwd)?;
     let git_ref_commit = git.get_ref_as_commit(git_ref).await?;
     debug!("found git commit: {}", git_ref_commit);
 
-    let octocrab = match AuthConfig::load() {
-        Ok(auth) => Arc::new(
-            OctocrabBuilder::new()
-                .user_access_token(auth.access_token)
-                .build()?,
-        ),
-        Err(err) => {
-            debug!("failed to load authentication config: {}", err);
-            debug!("falling back to default octocrab instance");
-
-            octocrab::instance()
-        }
+    let (provider, repo) = build_provider(&git, remote).await?;
+    let runs = provider
+        .list_checks_for_ref(&repo.owner, &repo.repo, &git_ref_commit)
+        .await?;
+
+    let git_ref_name = git.get_ref_as_name(git_ref).await?;
+
+    Ok((runs, git_ref_name))
+}
+
+// Lets the user pick a run for the current HEAD, downloads its log
+// output, and prints it (optionally just the last `tail` lines).
+async fn logs(cwd: Option<&std::path::Path>, remote: Option<&str>, tail: Option<usize>) -> Result<()> {
+    let git = Git::new(cwd)?;
+    let git_ref_commit = git.get_ref_as_commit("HEAD").await?;
+    let (provider, repo) = build_provider(&git, remote).await?;
+    let runs = provider
+        .list_checks_for_ref(&repo.owner, &repo.repo, &git_ref_commit)
+        .await?;
+
+    let items = runs.iter().map(|run| run.name.to_string()).collect::<Vec<_>>();
+    println!("Found {} runs for HEAD", runs.len());
+    let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+        .items(&items)
+        .default(0)
+        .interact_on_opt(&Term::stderr())?;
+
+    let Some(index) = selection else {
+        eprintln!("No run selected");
+        return Ok(());
     };
 
-    let github_repo = git.get_github_repo().await?;
+    let log = provider
+        .fetch_logs(&repo.owner, &repo.repo, &runs[index])
+        .await?;
 
-    let runs = octocrab
-        .checks(github_repo.owner, github_repo.repo)
-        .list_check_runs_for_git_ref(Commitish(git_ref_commit))
-        .send()
-        .await
-        .map_err(|err| {
-            if matches!(err, octocrab::Error::GitHub { .. }) {
-                println!("{}", "Failed to fetch check runs. Is your repository private? If so, you should log into your GitHub account with `fj login`".yellow());
-            }
+    let lines: Vec<&str> = log.lines().collect();
+    let start = tail.map(|n| lines.len().saturating_sub(n)).unwrap_or(0);
+    for line in &lines[start..] {
+        println!("{line}");
+    }
 
-            err
-        })?;
+    Ok(())
+}
 
-    let git_ref_name = git.get_ref_as_name(git_ref).await?;
+// Reruns failed/cancelled check runs for HEAD: every one of them with
+// `--failed`, otherwise whichever one the user picks.
+async fn rerun(cwd: Option<&std::path::Path>, remote: Option<&str>, failed: bool) -> Result<()> {
+    let git = Git::new(cwd)?;
+    let git_ref_commit = git.get_ref_as_commit("HEAD").await?;
+    let (provider, repo) = build_provider(&git, remote).await?;
+    let runs = provider
+        .list_checks_for_ref(&repo.owner, &repo.repo, &git_ref_commit)
+        .await?;
 
-    Ok((runs, git_ref_name))
+    let failing: Vec<&CheckRun> = runs
+        .iter()
+        .filter(|run| matches!(run.conclusion.as_deref(), Some("failure" | "cancelled")))
+        .collect();
+
+    if failing.is_empty() {
+        println!("No failed or cancelled check runs to rerun");
+        return Ok(());
+    }
+
+    let targets: Vec<&CheckRun> = if failed {
+        failing
+    } else {
+        require_interactive_stderr()?;
+
+        let items = failing.iter().map(|run| run.name.to_string()).collect::<Vec<_>>();
+        let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+            .items(&items)
+            .default(0)
+            .interact_on_opt(&Term::stderr())?;
+
+        let Some(index) = selection else {
+            eprintln!("No run selected");
+            return Ok(());
+        };
+
+        vec![failing[index]]
+    };
+
+    for run in targets {
+        provider.rerun(&repo.owner, &repo.repo, run).await?;
+        println!("Requested a rerun of `{}`", run.name);
+    }
+
+    Ok(())
 }
 
-// Idk kinda arbitrary
-const RETRY_LIMIT: usize = 10;
+const WATCH_BASE_POLL: Duration = Duration::from_secs(3);
+const WATCH_MAX_POLL: Duration = Duration::from_secs(30);
 
-#[derive(Debug, Serialize, Deserialize)]
-struct AuthConfig {
-    access_token: String,
-    token_type: String,
-    scope: Vec<String>,
+fn is_terminal_conclusion(run: &CheckRun) -> bool {
+    run.conclusion.is_some()
 }
 
-impl From<OAuth> for AuthConfig {
-    fn from(oauth: OAuth) -> Self {
-        Self {
-            access_token: oauth.access_token.expose_secret().to_string(),
-            token_type: oauth.token_type,
-            scope: oauth.scope,
+fn is_failing_conclusion(run: &CheckRun) -> bool {
+    matches!(
+        run.conclusion.as_deref(),
+        Some("failure" | "timed_out" | "cancelled")
+    )
+}
+
+// Re-renders the check list in place until every run reaches a terminal
+// conclusion (or `timeout` elapses), backing off the poll interval when
+// nothing has changed since the last look. Exits the process directly
+// so the exit code reflects the final state, same as a CI gate would.
+async fn watch(
+    cwd: Option<&std::path::Path>,
+    remote: Option<&str>,
+    timeout: Option<u64>,
+) -> Result<()> {
+    let term = Term::stdout();
+    let start = Instant::now();
+    let timeout = timeout.map(Duration::from_secs);
+    let mut poll_duration = WATCH_BASE_POLL;
+    let mut last_snapshot: Option<Vec<Option<String>>> = None;
+
+    loop {
+        let (runs, git_ref) = get_runs_for_ref(cwd, remote, "HEAD").await?;
+        term.clear_screen()?;
+        print_check_runs(&git_ref, &runs);
+
+        if !runs.is_empty() && runs.iter().all(is_terminal_conclusion) {
+            let failed = runs.iter().any(is_failing_conclusion);
+            std::process::exit(if failed { 1 } else { 0 });
         }
+
+        if let Some(timeout) = timeout {
+            if start.elapsed() >= timeout {
+                eprintln!(
+                    "{}",
+                    format!(
+                        "Timed out after {}s waiting for checks to finish",
+                        timeout.as_secs()
+                    )
+                    .yellow()
+                );
+                std::process::exit(1);
+            }
+        }
+
+        let snapshot: Vec<Option<String>> = runs.iter().map(|run| run.conclusion.clone()).collect();
+        poll_duration = if last_snapshot.as_ref() == Some(&snapshot) {
+            (poll_duration * 2).min(WATCH_MAX_POLL)
+        } else {
+            WATCH_BASE_POLL
+        };
+        last_snapshot = Some(snapshot);
+
+        println!(
+            "{}",
+            format!(
+                "Still waiting, polling again in {}s...",
+                poll_duration.as_secs()
+            )
+            .dimmed()
+        );
+        tokio::time::sleep(poll_duration).await;
     }
 }
 
-impl AuthConfig {
-    pub fn load() -> Result<Self> {
-        let base_dirs = xdg::BaseDirectories::with_prefix("fj")?;
-        let config_file_path = base_dirs.get_config_file("github.toml");
-        let config_file = fs::read_to_string(config_file_path)?;
-        Ok(toml::from_str(&config_file)?)
-    }
+// GitLab/Gitea don't have a device flow `fj` can drive, so logging into
+// one just means pasting a personal access token, scoped to that host,
+// into the same secure storage the GitHub flow uses.
+fn store_forge_token(forge: &str, host: String) -> Result<()> {
+    require_interactive_stderr()?;
+
+    let token = Password::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!("{forge} personal access token for {host}"))
+        .interact()?;
+
+    let mut auth = AuthConfig::load().unwrap_or_default();
+    auth.forge_tokens.insert(host.clone(), token);
+    auth.store()?;
+
+    println!("Stored a {forge} token for {host}");
+    Ok(())
 }
 
+// Idk kinda arbitrary
+const RETRY_LIMIT: usize = 10;
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
     let args = CLIArgs::parse();
 
+    // Mirror how other git tooling behaves in scripts vs a terminal:
+    // no ANSI codes once stdout isn't a tty, same as e.g. `git diff`.
+    colored::control::set_override(std::io::stdout().is_terminal());
+
     match args.command {
         CLICommand::Status => {
-            let (runs, git_ref) = get_runs_for_ref(args.cwd.as_deref(), "HEAD").await?;
-            print_check_runs(&git_ref, runs);
+            let (runs, git_ref) =
+                get_runs_for_ref(args.cwd.as_deref(), args.remote.as_deref(), "HEAD").await?;
+            match args.output {
+                OutputFormat::Text => print_check_runs(&git_ref, &runs),
+                OutputFormat::Json => print_check_runs_json(&runs)?,
+            }
+            std::process::exit(exit_code_for_runs(&runs));
         }
         CLICommand::Open => {
-            let (runs, git_ref) = get_runs_for_ref(args.cwd.as_deref(), "HEAD").await?;
+            require_interactive_stderr()?;
+            let (runs, git_ref) =
+                get_runs_for_ref(args.cwd.as_deref(), args.remote.as_deref(), "HEAD").await?;
             let items = runs
-                .check_runs
                 .iter()
                 .map(|run| run.name.to_string())
                 .collect::<Vec<_>>();
 
-            println!("Found {} runs for {}", runs.total_count, git_ref);
+            println!("Found {} runs for {}", runs.len(), git_ref);
             let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
                 .items(&items)
                 .default(0)
                 .interact_on_opt(&Term::stderr())?;
 
             if let Some(index) = selection {
-                if let Some(url) = &runs.check_runs[index].html_url {
+                if let Some(url) = &runs[index].url {
                     webbrowser::open(url)?;
                 } else {
-                    eprintln!("No url found for run `{}`", runs.check_runs[index].name);
+                    eprintln!("No url found for run `{}`", runs[index].name);
                 }
             } else {
                 eprintln!("No run selected");
             }
         }
+        CLICommand::Logs { tail } => {
+            require_interactive_stderr()?;
+            logs(args.cwd.as_deref(), args.remote.as_deref(), tail).await?;
+        }
         CLICommand::Branch => {
+            require_interactive_stderr()?;
             let git = Git::new(args.cwd.as_deref())?;
             let branches = git.get_branches().await?;
 
@@ -280,22 +433,45 @@ async fn main() -> Result<()> {
                 .interact_on_opt(&Term::stderr())?;
 
             if let Some(index) = selection {
-                let (runs, git_ref_name) =
-                    get_runs_for_ref(args.cwd.as_deref(), &branches[index]).await?;
-                print_check_runs(&git_ref_name, runs);
+                let (runs, git_ref_name) = get_runs_for_ref(
+                    args.cwd.as_deref(),
+                    args.remote.as_deref(),
+                    &branches[index],
+                )
+                .await?;
+                match args.output {
+                    OutputFormat::Text => print_check_runs(&git_ref_name, &runs),
+                    OutputFormat::Json => print_check_runs_json(&runs)?,
+                }
+                std::process::exit(exit_code_for_runs(&runs));
             } else {
                 eprintln!("No branch selected");
             }
         }
+        CLICommand::Watch { timeout } => {
+            watch(args.cwd.as_deref(), args.remote.as_deref(), timeout).await?;
+        }
+        CLICommand::Rerun { failed } => {
+            rerun(args.cwd.as_deref(), args.remote.as_deref(), failed).await?;
+        }
         CLICommand::Logout => {
-            let base_dirs = xdg::BaseDirectories::with_prefix("fj")?;
-            let config_file_path = base_dirs.place_config_file("github.toml")?;
-            fs::remove_file(&config_file_path)?;
+            AuthConfig::clear()?;
             println!("Successfully logged out");
         }
-        CLICommand::Login => {
+        CLICommand::Login {
+            host,
+            gitlab,
+            gitea,
+        } => {
+            if let Some(host) = gitlab {
+                return store_forge_token("GitLab", host);
+            }
+            if let Some(host) = gitea {
+                return store_forge_token("Gitea", host);
+            }
+
             let octocrab = octocrab::Octocrab::builder()
-                .base_uri("https://github.com")?
+                .base_uri(format!("https://{host}"))?
                 .add_header(ACCEPT, "application/json".to_string())
                 .build()?;
 
@@ -315,12 +491,9 @@ async fn main() -> Result<()> {
             for _ in 0..RETRY_LIMIT {
                 match device_codes.poll_once(&octocrab, &client_id).await {
                     Ok(Either::Left(auth)) => {
-                        let base_dirs = xdg::BaseDirectories::with_prefix("fj")?;
-                        let config_file_path = base_dirs.place_config_file("github.toml")?;
-
-                        debug!("config path is {}", config_file_path.display());
-                        let auth: AuthConfig = auth.into();
-                        fs::write(config_file_path, toml::to_string(&auth)?)?;
+                        let mut auth: AuthConfig = auth.into();
+                        auth.host = (host != "github.com").then(|| host.clone());
+                        auth.store()?;
 
                         println!("Successfully logged in!");
                         break;
@@ -0,0 +1,76 @@
+use super::{CheckRun, CiProvider};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Deserialize;
+
+pub struct GiteaProvider {
+    base_url: String,
+    token: Option<String>,
+    client: reqwest::Client,
+}
+
+impl GiteaProvider {
+    pub fn new(base_url: String, token: Option<String>) -> Self {
+        Self {
+            base_url,
+            token,
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Status {
+    id: u64,
+    context: String,
+    status: String,
+    target_url: Option<String>,
+}
+
+#[async_trait]
+impl CiProvider for GiteaProvider {
+    async fn list_checks_for_ref(
+        &self,
+        owner: &str,
+        repo: &str,
+        commit: &str,
+    ) -> Result<Vec<CheckRun>> {
+        let mut req = self.client.get(format!(
+            "{}/api/v1/repos/{owner}/{repo}/commits/{commit}/statuses",
+            self.base_url
+        ));
+        if let Some(token) = &self.token {
+            req = req.header("Authorization", format!("token {token}"));
+        }
+
+        let statuses: Vec<Status> = req.send().await?.error_for_status()?.json().await?;
+
+        Ok(statuses
+            .into_iter()
+            .map(|status| {
+                let (status_field, conclusion) = normalize_status(&status.status);
+                CheckRun {
+                    name: status.context,
+                    status: status_field,
+                    conclusion,
+                    url: status.target_url,
+                    id: Some(status.id),
+                }
+            })
+            .collect())
+    }
+
+    fn auth(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+}
+
+fn normalize_status(status: &str) -> (Option<String>, Option<String>) {
+    match status {
+        "success" => (Some("completed".to_string()), Some("success".to_string())),
+        "failure" | "error" => (Some("completed".to_string()), Some("failure".to_string())),
+        "warning" => (Some("completed".to_string()), Some("neutral".to_string())),
+        "pending" => (Some("in_progress".to_string()), None),
+        other => (Some(other.to_string()), None),
+    }
+}
@@ -0,0 +1,151 @@
+use super::{CheckRun, CiProvider};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use colored::*;
+use octocrab::params::repos::Commitish;
+use octocrab::{Octocrab, OctocrabBuilder};
+use reqwest::header::{ACCEPT, AUTHORIZATION, USER_AGENT};
+use std::sync::Arc;
+
+pub struct GitHubProvider {
+    client: Arc<Octocrab>,
+    token: Option<String>,
+    // Base URL for REST calls we make directly (i.e. not through
+    // octocrab), so logs work against a GitHub Enterprise install too.
+    api_base: String,
+}
+
+impl GitHubProvider {
+    pub fn new(host: &str, token: Option<String>) -> Result<Self> {
+        let enterprise_base_uri = (host != "github.com").then(|| format!("https://{host}/api/v3"));
+        let api_base = enterprise_base_uri
+            .clone()
+            .unwrap_or_else(|| "https://api.github.com".to_string());
+
+        let client = if token.is_some() || enterprise_base_uri.is_some() {
+            let mut builder = OctocrabBuilder::new();
+            if let Some(token) = &token {
+                builder = builder.user_access_token(token.clone());
+            }
+            if let Some(base_uri) = &enterprise_base_uri {
+                builder = builder.base_uri(base_uri)?;
+            }
+            Arc::new(builder.build()?)
+        } else {
+            octocrab::instance()
+        };
+
+        Ok(Self {
+            client,
+            token,
+            api_base,
+        })
+    }
+}
+
+#[async_trait]
+impl CiProvider for GitHubProvider {
+    async fn list_checks_for_ref(
+        &self,
+        owner: &str,
+        repo: &str,
+        commit: &str,
+    ) -> Result<Vec<CheckRun>> {
+        let runs = self
+            .client
+            .checks(owner, repo)
+            .list_check_runs_for_git_ref(Commitish(commit.to_string()))
+            .send()
+            .await
+            .map_err(|err| {
+                if matches!(err, octocrab::Error::GitHub { .. }) {
+                    println!("{}", "Failed to fetch check runs. Is your repository private? If so, you should log into your GitHub account with `fj login`".yellow());
+                }
+
+                err
+            })?;
+
+        Ok(runs
+            .check_runs
+            .into_iter()
+            .map(|run| CheckRun {
+                name: run.name,
+                status: Some(run.status),
+                conclusion: run.conclusion,
+                url: run.html_url,
+                id: Some(u64::from(run.id)),
+            })
+            .collect())
+    }
+
+    fn auth(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    async fn fetch_logs(&self, owner: &str, repo: &str, run: &CheckRun) -> Result<String> {
+        let job_id = run
+            .id
+            .ok_or_else(|| anyhow!("check run has no backing job id"))?;
+        let token = self.token.as_deref().ok_or_else(|| {
+            anyhow!("fetching logs requires an authenticated client; run `fj login` first")
+        })?;
+
+        // octocrab doesn't expose this endpoint directly (it's a redirect
+        // to a plain-text blob), so we hit it with a plain HTTP client.
+        let log = reqwest::Client::new()
+            .get(format!(
+                "{}/repos/{owner}/{repo}/actions/jobs/{job_id}/logs",
+                self.api_base
+            ))
+            .header(AUTHORIZATION, format!("Bearer {token}"))
+            .header(USER_AGENT, "fj")
+            .header(ACCEPT, "application/vnd.github+json")
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        Ok(log)
+    }
+
+    async fn rerun(&self, owner: &str, repo: &str, run: &CheckRun) -> Result<()> {
+        let job_id = run
+            .id
+            .ok_or_else(|| anyhow!("check run has no backing job id"))?;
+        let token = self.token.as_deref().ok_or_else(|| {
+            anyhow!("rerunning jobs requires an authenticated client; run `fj login` first")
+        })?;
+
+        let client = reqwest::Client::new();
+        let with_auth = |req: reqwest::RequestBuilder| {
+            req.header(AUTHORIZATION, format!("Bearer {token}"))
+                .header(USER_AGENT, "fj")
+                .header(ACCEPT, "application/vnd.github+json")
+        };
+
+        // Use the single-job rerun endpoint rather than the parent
+        // workflow run's `rerun-failed-jobs`: the latter reruns every
+        // failed job in the run, not just this one, so calling it once
+        // per selected check run either reruns jobs the caller never
+        // asked for, or (when two failing checks share a run) fails on
+        // the second call because GitHub refuses to rerun a run that's
+        // already in progress.
+        let response = with_auth(client.post(format!(
+            "{}/repos/{owner}/{repo}/actions/jobs/{job_id}/rerun",
+            self.api_base
+        )))
+        .send()
+        .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "failed to rerun job {}: {}",
+                job_id,
+                response.status()
+            ));
+        }
+
+        Ok(())
+    }
+}
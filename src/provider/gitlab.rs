@@ -0,0 +1,113 @@
+use super::{CheckRun, CiProvider};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Deserialize;
+
+pub struct GitLabProvider {
+    base_url: String,
+    token: Option<String>,
+    client: reqwest::Client,
+}
+
+impl GitLabProvider {
+    pub fn new(base_url: String, token: Option<String>) -> Self {
+        Self {
+            base_url,
+            token,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn get(&self, url: impl AsRef<str>) -> reqwest::RequestBuilder {
+        let req = self.client.get(url.as_ref());
+        match &self.token {
+            Some(token) => req.header("PRIVATE-TOKEN", token),
+            None => req,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Pipeline {
+    id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Job {
+    id: u64,
+    name: String,
+    status: String,
+    web_url: Option<String>,
+}
+
+#[async_trait]
+impl CiProvider for GitLabProvider {
+    async fn list_checks_for_ref(
+        &self,
+        owner: &str,
+        repo: &str,
+        commit: &str,
+    ) -> Result<Vec<CheckRun>> {
+        let project_id = urlencoding::encode(&format!("{owner}/{repo}")).into_owned();
+
+        let pipelines: Vec<Pipeline> = self
+            .get(format!(
+                "{}/api/v4/projects/{project_id}/pipelines?sha={commit}",
+                self.base_url
+            ))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let Some(pipeline) = pipelines.into_iter().next() else {
+            return Ok(Vec::new());
+        };
+
+        let jobs: Vec<Job> = self
+            .get(format!(
+                "{}/api/v4/projects/{project_id}/pipelines/{}/jobs",
+                self.base_url, pipeline.id
+            ))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(jobs
+            .into_iter()
+            .map(|job| {
+                let (status, conclusion) = normalize_status(&job.status);
+                CheckRun {
+                    name: job.name,
+                    status,
+                    conclusion,
+                    url: job.web_url,
+                    id: Some(job.id),
+                }
+            })
+            .collect())
+    }
+
+    fn auth(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+}
+
+// GitLab job statuses don't map 1:1 onto GitHub's status/conclusion
+// split, so we bucket the terminal ones as a conclusion and leave
+// everything still running as a bare status.
+fn normalize_status(status: &str) -> (Option<String>, Option<String>) {
+    match status {
+        "success" => (Some("completed".to_string()), Some("success".to_string())),
+        "failed" => (Some("completed".to_string()), Some("failure".to_string())),
+        "canceled" => (
+            Some("completed".to_string()),
+            Some("cancelled".to_string()),
+        ),
+        "skipped" => (Some("completed".to_string()), Some("neutral".to_string())),
+        other => (Some(other.to_string()), None),
+    }
+}
@@ -0,0 +1,75 @@
+mod gitea;
+mod github;
+mod gitlab;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::Serialize;
+
+pub use gitea::GiteaProvider;
+pub use github::GitHubProvider;
+pub use gitlab::GitLabProvider;
+
+/// A single CI check/pipeline job, normalized across forges so
+/// `print_check_runs` doesn't need to know which one produced it.
+/// Also the schema for `--output json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckRun {
+    pub name: String,
+    pub status: Option<String>,
+    pub conclusion: Option<String>,
+    pub url: Option<String>,
+    // The forge's own id for the backing job, if it has one. Needed to
+    // fetch logs, which are addressed by job id rather than check name.
+    // Not part of the `--output json` schema.
+    #[serde(skip)]
+    pub id: Option<u64>,
+}
+
+/// A source of CI check runs for a given commit, implemented once per
+/// forge. `select_provider` picks the right implementation based on the
+/// host parsed out of the git remote.
+#[async_trait]
+pub trait CiProvider {
+    async fn list_checks_for_ref(
+        &self,
+        owner: &str,
+        repo: &str,
+        commit: &str,
+    ) -> Result<Vec<CheckRun>>;
+
+    /// The token this provider is authenticating with, if any.
+    fn auth(&self) -> Option<&str>;
+
+    /// Fetches the raw log output for a check run's backing job.
+    /// Forges without a logs API of their own can leave this
+    /// unimplemented.
+    async fn fetch_logs(&self, _owner: &str, _repo: &str, _run: &CheckRun) -> Result<String> {
+        Err(anyhow!("fetching logs isn't supported for this forge yet"))
+    }
+
+    /// Re-dispatches a check run's backing job. Forges without a rerun
+    /// API of their own can leave this unimplemented.
+    async fn rerun(&self, _owner: &str, _repo: &str, _run: &CheckRun) -> Result<()> {
+        Err(anyhow!("rerunning jobs isn't supported for this forge yet"))
+    }
+}
+
+/// Picks a `CiProvider` for the given remote host. Anything that isn't
+/// recognized as GitLab or Gitea falls back to GitHub, since that's the
+/// only forge `fj` used to support.
+pub fn select_provider(host: &str, token: Option<String>) -> Result<Box<dyn CiProvider>> {
+    if host.contains("gitlab") {
+        Ok(Box::new(GitLabProvider::new(
+            format!("https://{host}"),
+            token,
+        )))
+    } else if host.contains("gitea") {
+        Ok(Box::new(GiteaProvider::new(
+            format!("https://{host}"),
+            token,
+        )))
+    } else {
+        Ok(Box::new(GitHubProvider::new(host, token)?))
+    }
+}